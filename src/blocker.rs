@@ -0,0 +1,172 @@
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// Something a `Monitor` can park a waiter on and later wake up.
+///
+/// Blocking channel operations (`recv_until`, `send_until`, `Select::ready`)
+/// used to assume an OS thread throughout: `Monitor` registered the calling
+/// thread and `wakeup_one`/`wakeup_all` called `Thread::unpark` on it. That
+/// breaks down for green-thread/coroutine schedulers, where parking the
+/// carrier OS thread would freeze every other task scheduled onto it instead
+/// of just the one waiting on the channel.
+///
+/// `Blocker` is what `Monitor` stores per registered waiter instead (see
+/// `monitor::Monitor`), and `Coroutine` lets a scheduler plug in its own
+/// park/unpark without `Monitor` or the channel flavors knowing anything
+/// about that scheduler. It's cheap to clone: a `Monitor` registration takes
+/// its own copy of whatever is currently installed via `set`.
+#[derive(Clone)]
+pub enum Blocker {
+    /// The calling OS thread, parked via `thread::park`/`park_timeout` and
+    /// woken via `Thread::unpark`.
+    Thread(Thread, usize),
+    /// A cooperatively-scheduled task, parked and woken by its own runtime.
+    Coroutine(Arc<Coroutine>),
+}
+
+/// Implemented by a coroutine/task handle so its runtime can suspend and
+/// resume it in response to channel readiness.
+pub trait Coroutine: Send + Sync {
+    /// A token identifying this task, unique among every other task a single
+    /// scheduler could have registered on the same `Monitor` at once.
+    ///
+    /// `Blocker::id` tags it to keep it from colliding with an OS thread's
+    /// token, so implementors don't need any awareness of this crate's
+    /// thread-token counter. Every `Coroutine` implementor's ids get the same
+    /// tag, so ids are not kept apart between different implementors.
+    fn id(&self) -> usize;
+
+    /// Suspends the current task until woken or `deadline` elapses, returning
+    /// `false` on timeout (mirrors `thread::park_timeout`'s spurious-wakeup
+    /// contract: a `true` result doesn't guarantee the channel is ready,
+    /// callers must re-check).
+    fn park(&self, deadline: Option<Instant>) -> bool;
+
+    /// Resumes the task parked via `park`.
+    fn unpark(&self);
+}
+
+impl Blocker {
+    /// The default blocker: the OS thread currently executing.
+    pub fn current_thread() -> Blocker {
+        Blocker::Thread(thread::current(), thread_token())
+    }
+
+    /// This blocker's id, as handed to `Monitor::wakeup_one`/`wakeup_all` to
+    /// avoid waking the caller's own registration.
+    ///
+    /// `Thread` and `Coroutine` ids are tagged into disjoint spaces (even vs.
+    /// odd) before being compared anywhere: `thread_token()` already hands
+    /// out even numbers, and a `Coroutine`'s own id is shifted up and tagged
+    /// odd here. Without this, a small sequential `Coroutine::id()` (the
+    /// obvious way to implement it) would collide with this crate's own
+    /// small sequential thread tokens essentially by construction, and
+    /// `Monitor::wakeup_one`/`unregister` would exclude or unregister the
+    /// wrong waiter on the collision.
+    pub fn id(&self) -> usize {
+        match *self {
+            Blocker::Thread(_, token) => token,
+            Blocker::Coroutine(ref c) => (c.id() << 1) | 1,
+        }
+    }
+
+    pub fn park(&self, deadline: Option<Instant>) -> bool {
+        match *self {
+            Blocker::Thread(..) => park_thread(deadline),
+            Blocker::Coroutine(ref c) => c.park(deadline),
+        }
+    }
+
+    pub fn unpark(&self) {
+        match *self {
+            Blocker::Thread(ref t, _) => t.unpark(),
+            Blocker::Coroutine(ref c) => c.unpark(),
+        }
+    }
+}
+
+fn park_thread(deadline: Option<Instant>) -> bool {
+    match deadline {
+        None => {
+            thread::park();
+            true
+        }
+        Some(end) => {
+            let now = Instant::now();
+            if now >= end {
+                return false;
+            }
+            thread::park_timeout(end - now);
+            Instant::now() < end
+        }
+    }
+}
+
+/// Assigns each OS thread a stable id the first time it's needed, tagged
+/// even so it can never collide with a `Coroutine`'s (tagged odd by
+/// `Blocker::id`).
+fn thread_token() -> usize {
+    thread_local! {
+        static TOKEN: usize = next_thread_token() << 1;
+    }
+    TOKEN.with(|t| *t)
+}
+
+fn next_thread_token() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+thread_local! {
+    /// The blocker that `current`/`wait_until` act on for the current thread.
+    /// Defaults to parking the OS thread; a coroutine scheduler overrides it
+    /// with `set` before running task code that may touch a channel.
+    static CONTEXT: RefCell<Option<Blocker>> = RefCell::new(None);
+}
+
+/// Installs `blocker` as the current thread's blocker for the duration of
+/// channel operations run on it. Coroutine schedulers call this once when
+/// resuming a task, before that task's code can reach `recv_until`,
+/// `send_until`, or `Select::ready`.
+pub fn set(blocker: Blocker) {
+    CONTEXT.with(|cell| *cell.borrow_mut() = Some(blocker));
+}
+
+/// Removes whatever blocker was installed with `set`, reverting to the
+/// default of parking the calling OS thread.
+pub fn clear() {
+    CONTEXT.with(|cell| *cell.borrow_mut() = None);
+}
+
+/// Returns the blocker that channel operations on the current thread should
+/// register and park on: whatever a coroutine scheduler installed via `set`,
+/// or the calling OS thread by default. `Monitor::register` calls this to
+/// get the value it actually stores per waiter.
+pub fn current() -> Blocker {
+    CONTEXT.with(|cell| match *cell.borrow() {
+        Some(ref blocker) => blocker.clone(),
+        None => Blocker::current_thread(),
+    })
+}
+
+/// Called before a waiter registers on a channel's monitor. Does nothing: a
+/// stale wakeup from a previous round is not cleared here, and may make the
+/// next `wait_until` return early.
+pub fn reset() {
+    // A fresh `park`/`park_timeout` call (thread or coroutine) already
+    // consumes at most one pending wakeup, so there's nothing to clear
+    // proactively here; kept as a named call so flavors don't need to know
+    // that detail of whichever `Blocker` kind ends up installed.
+}
+
+/// Parks the current blocker until woken or `deadline` elapses. Channel
+/// flavors call this instead of assuming `thread::park`, so the exact same
+/// `Queue<T>`/`Zero<T>`/`Select` code works whether it's running on a plain
+/// OS thread or a task scheduled by a coroutine runtime that installed its
+/// own `Blocker` via `set`.
+pub fn wait_until(deadline: Option<Instant>) -> bool {
+    current().park(deadline)
+}
@@ -11,7 +11,7 @@ use coco::epoch::{self, Atomic, Owned};
 use err::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError};
 use impls::Channel;
 use monitor::Monitor;
-use actor;
+use blocker;
 
 /// A single node in a queue.
 struct Node<T> {
@@ -37,6 +37,21 @@ pub struct Queue<T> {
     /// TODO
     closed: AtomicBool,
     receivers: Monitor,
+    /// Senders parked on `send_until`, waiting for a free slot.
+    senders: Monitor,
+    /// The maximum number of items the queue can hold, or `None` if unbounded.
+    cap: Option<usize>,
+
+    /// Number of live `Sender` handles.
+    sender_count: AtomicUsize,
+    /// Number of live `Receiver` handles.
+    receiver_count: AtomicUsize,
+    /// Set once `sender_count` has dropped to zero: no more items will ever
+    /// be pushed, though already-buffered ones are still there to drain.
+    senders_done: AtomicBool,
+    /// Set once `receiver_count` has dropped to zero: nothing will ever read
+    /// from this queue again.
+    receivers_done: AtomicBool,
 
     _marker: PhantomData<T>,
 }
@@ -46,6 +61,10 @@ unsafe impl<T: Send> Sync for Queue<T> {}
 
 impl<T> Queue<T> {
     pub fn new() -> Self {
+        Self::with_capacity(None)
+    }
+
+    pub fn with_capacity(cap: Option<usize>) -> Self {
         // Initialize the internal representation of the queue.
         let queue = Queue {
             head: Atomic::null(),
@@ -54,6 +73,13 @@ impl<T> Queue<T> {
             _pad1: unsafe { mem::uninitialized() },
             closed: AtomicBool::new(false),
             receivers: Monitor::new(),
+            senders: Monitor::new(),
+            cap: cap,
+
+            sender_count: AtomicUsize::new(1),
+            receiver_count: AtomicUsize::new(1),
+            senders_done: AtomicBool::new(false),
+            receivers_done: AtomicBool::new(false),
 
             sends: AtomicUsize::new(0),
             recvs: AtomicUsize::new(0),
@@ -78,7 +104,33 @@ impl<T> Queue<T> {
         queue
     }
 
-    fn push(&self, value: T) {
+    /// Pushes `value` onto the queue, returning it back if the queue is at
+    /// capacity.
+    ///
+    /// For a bounded queue, the slot is reserved by CAS-ing `sends` up front
+    /// (subject to the `cap` check), rather than checking `len()` and then
+    /// pushing unconditionally: two concurrent `push` calls racing a plain
+    /// check-then-act `len()` read could both observe room and both
+    /// proceed, growing the queue past `cap`. The CAS makes the reservation
+    /// atomic, so only as many callers as there is room for ever get past
+    /// the loop below (`recvs` only ever increases, so reading it stale just
+    /// makes the check more conservative, never overcommits it).
+    fn push(&self, value: T) -> Result<(), T> {
+        if let Some(cap) = self.cap {
+            loop {
+                let sends = self.sends.load(SeqCst);
+                let recvs = self.recvs.load(SeqCst);
+                if sends.wrapping_sub(recvs) >= cap {
+                    return Err(value);
+                }
+                if self.sends.compare_and_swap(sends, sends.wrapping_add(1), SeqCst) == sends {
+                    break;
+                }
+            }
+        } else {
+            self.sends.fetch_add(1, SeqCst);
+        }
+
         let mut node = Owned::new(Node {
             value: value,
             next: Atomic::null(),
@@ -88,10 +140,11 @@ impl<T> Queue<T> {
             epoch::unprotected(|scope| {
                 let new = node.into_ptr(scope);
                 let old = self.tail.swap(new, SeqCst, scope);
-                self.sends.fetch_add(1, SeqCst);
                 old.deref().next.store(new, SeqCst);
             })
         }
+
+        Ok(())
     }
 
     fn pop(&self) -> Option<T> {
@@ -126,6 +179,7 @@ impl<T> Queue<T> {
                             {
                                 self.recvs.fetch_add(1, SeqCst);
                                 Vec::from_raw_parts(head.as_raw() as *mut Node<T>, 0, 1);
+                                self.senders.wakeup_one(self.id());
                                 return Some(value);
                             }
                             mem::forget(value);
@@ -155,6 +209,7 @@ impl<T> Queue<T> {
                         {
                             self.recvs.fetch_add(1, SeqCst);
                             scope.defer_free(head);
+                            self.senders.wakeup_one(self.id());
                             return Some(ptr::read(&next.deref().value));
                         }
                     }
@@ -168,16 +223,71 @@ impl<T> Queue<T> {
     pub fn monitor_rx(&self) -> &Monitor {
         &self.receivers
     }
+
+    pub fn monitor_tx(&self) -> &Monitor {
+        &self.senders
+    }
+
+    /// Registers another live `Sender` handle. Called when a `Sender` is cloned.
+    pub fn acquire_sender(&self) {
+        self.sender_count.fetch_add(1, SeqCst);
+    }
+
+    /// Records a `Sender` handle going away. Called when a `Sender` is dropped.
+    ///
+    /// Once the last one is gone, the queue can never receive another item:
+    /// receivers still drain whatever is already buffered, but afterwards
+    /// see `RecvError`/`Disconnected` instead of blocking forever.
+    pub fn release_sender(&self) {
+        if self.sender_count.fetch_sub(1, SeqCst) == 1 {
+            self.senders_done.store(true, SeqCst);
+            self.receivers.wakeup_all(self.id());
+        }
+    }
+
+    /// Registers another live `Receiver` handle. Called when a `Receiver` is cloned.
+    pub fn acquire_receiver(&self) {
+        self.receiver_count.fetch_add(1, SeqCst);
+    }
+
+    /// Records a `Receiver` handle going away. Called when a `Receiver` is dropped.
+    ///
+    /// Once the last one is gone, nothing will ever read from this queue
+    /// again, so senders see `Disconnected` immediately rather than
+    /// buffering items (or blocking on a full bounded queue) for nobody.
+    pub fn release_receiver(&self) {
+        if self.receiver_count.fetch_sub(1, SeqCst) == 1 {
+            self.receivers_done.store(true, SeqCst);
+            self.senders.wakeup_all(self.id());
+        }
+    }
+
+    /// Whether `send`/`try_send` should report this queue as disconnected:
+    /// either explicitly `close`d, or every `Receiver` has been dropped.
+    fn send_disconnected(&self) -> bool {
+        self.closed.load(SeqCst) || self.receivers_done.load(SeqCst)
+    }
+
+    /// Whether `recv`/`try_recv` should report this queue as disconnected
+    /// once drained: either explicitly `close`d, or every `Sender` has been
+    /// dropped.
+    fn recv_disconnected(&self) -> bool {
+        self.closed.load(SeqCst) || self.senders_done.load(SeqCst)
+    }
 }
 
 impl<T> Channel<T> for Queue<T> {
     fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
-        if self.closed.load(SeqCst) {
-            Err(TrySendError::Disconnected(value))
-        } else {
-            self.push(value);
-            self.receivers.wakeup_one(self.id());
-            Ok(())
+        if self.send_disconnected() {
+            return Err(TrySendError::Disconnected(value));
+        }
+
+        match self.push(value) {
+            Ok(()) => {
+                self.receivers.wakeup_one(self.id());
+                Ok(())
+            }
+            Err(value) => Err(TrySendError::Full(value)),
         }
     }
 
@@ -186,19 +296,38 @@ impl<T> Channel<T> for Queue<T> {
         mut value: T,
         deadline: Option<Instant>,
     ) -> Result<(), SendTimeoutError<T>> {
-        if self.closed.load(SeqCst) {
-            Err(SendTimeoutError::Disconnected(value))
-        } else {
-            self.push(value);
-            self.receivers.wakeup_one(self.id());
-            Ok(())
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(v)) => return Err(SendTimeoutError::Disconnected(v)),
+                Err(TrySendError::Full(v)) => value = v,
+            }
+
+            let now = Instant::now();
+            if let Some(end) = deadline {
+                if now >= end {
+                    return Err(SendTimeoutError::Timeout(value));
+                }
+            }
+
+            blocker::reset();
+            self.senders.register();
+
+            if !self.send_disconnected() && self.is_full() {
+                if !blocker::wait_until(deadline) {
+                    self.senders.unregister();
+                    return Err(SendTimeoutError::Timeout(value));
+                }
+            } else {
+                self.senders.unregister();
+            }
         }
     }
 
     fn try_recv(&self) -> Result<T, TryRecvError> {
         match self.pop() {
             None => {
-                if self.closed.load(SeqCst) {
+                if self.recv_disconnected() {
                     Err(TryRecvError::Disconnected)
                 } else {
                     Err(TryRecvError::Empty)
@@ -223,11 +352,11 @@ impl<T> Channel<T> for Queue<T> {
                 }
             }
 
-            actor::reset();
+            blocker::reset();
             self.receivers.register();
 
-            if !self.is_closed() && self.is_empty() {
-                if !actor::wait_until(deadline) {
+            if !self.recv_disconnected() && self.is_empty() {
+                if !blocker::wait_until(deadline) {
                     self.receivers.unregister();
                     return Err(RecvTimeoutError::Timeout);
                 }
@@ -255,11 +384,14 @@ impl<T> Channel<T> for Queue<T> {
     }
 
     fn is_full(&self) -> bool {
-        false
+        match self.cap {
+            None => false,
+            Some(cap) => self.len() >= cap,
+        }
     }
 
     fn capacity(&self) -> Option<usize> {
-        None
+        self.cap
     }
 
     fn close(&self) -> bool {
@@ -268,11 +400,12 @@ impl<T> Channel<T> for Queue<T> {
         }
 
         self.receivers.wakeup_all(self.id());
+        self.senders.wakeup_all(self.id());
         true
     }
 
     fn is_closed(&self) -> bool {
-        self.closed.load(SeqCst)
+        self.closed.load(SeqCst) || self.senders_done.load(SeqCst) || self.receivers_done.load(SeqCst)
     }
 }
 
@@ -306,9 +439,12 @@ mod tests {
 
     use crossbeam;
 
+    use impls::Channel;
     use unbounded;
     use err::*;
 
+    use super::Queue;
+
     // TODO: drop test
 
     fn ms(ms: u64) -> Duration {
@@ -420,6 +556,125 @@ mod tests {
         });
     }
 
+    #[test]
+    fn bounded_try_send_full() {
+        let q = Queue::with_capacity(Some(1));
+
+        assert_eq!(q.capacity(), Some(1));
+        assert!(!q.is_full());
+        assert_eq!(Channel::try_send(&q, 1), Ok(()));
+        assert!(q.is_full());
+        assert_eq!(Channel::try_send(&q, 2), Err(TrySendError::Full(2)));
+
+        assert_eq!(Channel::try_recv(&q), Ok(1));
+        assert!(!q.is_full());
+        assert_eq!(Channel::try_send(&q, 2), Ok(()));
+    }
+
+    #[test]
+    fn bounded_try_send_never_exceeds_capacity_under_contention() {
+        const CAP: usize = 4;
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 2_000;
+
+        let q = Queue::with_capacity(Some(CAP));
+        let over = AtomicUsize::new(0);
+
+        crossbeam::scope(|s| {
+            let q = &q;
+            let over = &over;
+            for _ in 0..THREADS {
+                s.spawn(move || {
+                    for i in 0..PER_THREAD {
+                        if Channel::try_send(q, i).is_ok() {
+                            if q.len() > CAP {
+                                over.fetch_add(1, SeqCst);
+                            }
+                        }
+                        let _ = q.try_recv();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(over.load(SeqCst), 0);
+    }
+
+    #[test]
+    fn bounded_send_until_blocks() {
+        let q = Queue::with_capacity(Some(1));
+        q.try_send(1).unwrap();
+
+        assert_eq!(
+            q.send_until(2, Some(Instant::now() + ms(100))),
+            Err(SendTimeoutError::Timeout(2))
+        );
+
+        crossbeam::scope(|s| {
+            let q = &q;
+            s.spawn(move || {
+                assert_eq!(q.send_until(2, None), Ok(()));
+            });
+            s.spawn(move || {
+                thread::sleep(ms(100));
+                assert_eq!(q.try_recv(), Ok(1));
+            });
+        });
+
+        assert_eq!(q.try_recv(), Ok(2));
+    }
+
+    #[test]
+    fn refcounted_disconnect_drains_before_erroring() {
+        let q = Queue::new();
+        q.acquire_sender();
+
+        Channel::try_send(&q, 1).unwrap();
+        Channel::try_send(&q, 2).unwrap();
+
+        q.release_sender();
+        assert!(!Channel::is_closed(&q));
+
+        q.release_sender();
+
+        // Buffered items still come through after the last sender is gone.
+        assert_eq!(Channel::try_recv(&q), Ok(1));
+        assert_eq!(Channel::try_recv(&q), Ok(2));
+        assert_eq!(Channel::try_recv(&q), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn refcounted_last_receiver_gone_fails_sends() {
+        let q = Queue::new();
+        q.acquire_receiver();
+
+        q.release_receiver();
+        assert_eq!(Channel::try_send(&q, 1), Ok(()));
+
+        q.release_receiver();
+        assert_eq!(Channel::try_send(&q, 1), Err(TrySendError::Disconnected(1)));
+    }
+
+    #[test]
+    fn refcounted_last_receiver_gone_wakes_blocked_sender() {
+        let q = Queue::with_capacity(Some(1));
+        Channel::try_send(&q, 1).unwrap();
+
+        crossbeam::scope(|s| {
+            let q = &q;
+            s.spawn(move || {
+                assert_eq!(
+                    q.send_until(2, Some(Instant::now() + ms(5_000))),
+                    Err(SendTimeoutError::Disconnected(2))
+                );
+            });
+            s.spawn(move || {
+                thread::sleep(ms(100));
+                q.release_receiver();
+            });
+        });
+    }
+
     #[test]
     fn spsc() {
         const COUNT: usize = 100_000;
@@ -0,0 +1,362 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::time::Instant;
+
+use err::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError};
+use impls::Channel;
+use monitor::Monitor;
+use blocker;
+
+/// A sender's own handoff slot, carrying the value it wants to give away
+/// directly to whichever receiver claims it — there is no shared slot for
+/// receivers to contend over.
+struct SenderSlot<T> {
+    value: Mutex<Option<T>>,
+}
+
+/// A zero-capacity rendezvous channel.
+///
+/// `send` only completes once a receiver is registered on the receivers
+/// `Monitor`: until then a blocking sender registers on the senders
+/// `Monitor` and parks, holding no slot. Once a receiver is registered,
+/// `try_send` puts the value in its own `SenderSlot` at the back of
+/// `waiting_senders` and returns at once; it does not wait for a receiver to
+/// claim the slot. A receiver claims the first one in line directly out of
+/// `waiting_senders` — a per-sender handoff rather than the Michael-Scott
+/// node list `Queue` uses, or a single shared slot every sender and receiver
+/// would have to contend over.
+///
+/// `waiting_senders` being empty or not is exactly "is there a value to
+/// claim right now", and `receivers.is_empty()` is exactly "is anyone
+/// parked to claim one" — both read directly off the data they describe,
+/// so there's no separate side counter that could drift out of sync with
+/// reality.
+pub struct Zero<T> {
+    /// Values handed off by `try_send` (which `send_until` also goes
+    /// through), in arrival order; a receiver claims the front one.
+    waiting_senders: Mutex<VecDeque<Arc<SenderSlot<T>>>>,
+
+    closed: AtomicBool,
+    receivers: Monitor,
+    senders: Monitor,
+
+    /// Number of live `Sender` handles.
+    sender_count: AtomicUsize,
+    /// Number of live `Receiver` handles.
+    receiver_count: AtomicUsize,
+    /// Set once `sender_count` has dropped to zero: no sender will ever show
+    /// up to rendezvous with a parked receiver again.
+    senders_done: AtomicBool,
+    /// Set once `receiver_count` has dropped to zero: no receiver will ever
+    /// show up to rendezvous with a parked sender again.
+    receivers_done: AtomicBool,
+}
+
+unsafe impl<T: Send> Send for Zero<T> {}
+unsafe impl<T: Send> Sync for Zero<T> {}
+
+impl<T> Zero<T> {
+    pub fn new() -> Self {
+        Zero {
+            waiting_senders: Mutex::new(VecDeque::new()),
+
+            closed: AtomicBool::new(false),
+            receivers: Monitor::new(),
+            senders: Monitor::new(),
+
+            sender_count: AtomicUsize::new(1),
+            receiver_count: AtomicUsize::new(1),
+            senders_done: AtomicBool::new(false),
+            receivers_done: AtomicBool::new(false),
+        }
+    }
+
+    pub fn monitor_rx(&self) -> &Monitor {
+        &self.receivers
+    }
+
+    pub fn monitor_tx(&self) -> &Monitor {
+        &self.senders
+    }
+
+    /// Registers another live `Sender` handle. Called when a `Sender` is cloned.
+    pub fn acquire_sender(&self) {
+        self.sender_count.fetch_add(1, SeqCst);
+    }
+
+    /// Records a `Sender` handle going away. Called when a `Sender` is dropped.
+    ///
+    /// Once the last one is gone, no sender will ever show up to complete a
+    /// rendezvous again, so a receiver parked in `recv_until` sees
+    /// `Disconnected` instead of blocking forever.
+    pub fn release_sender(&self) {
+        if self.sender_count.fetch_sub(1, SeqCst) == 1 {
+            self.senders_done.store(true, SeqCst);
+            self.receivers.wakeup_all(self.id());
+        }
+    }
+
+    /// Registers another live `Receiver` handle. Called when a `Receiver` is cloned.
+    pub fn acquire_receiver(&self) {
+        self.receiver_count.fetch_add(1, SeqCst);
+    }
+
+    /// Records a `Receiver` handle going away. Called when a `Receiver` is dropped.
+    ///
+    /// Once the last one is gone, no receiver will ever show up to complete a
+    /// rendezvous again, so a sender parked in `send_until` sees
+    /// `Disconnected` instead of blocking forever.
+    pub fn release_receiver(&self) {
+        if self.receiver_count.fetch_sub(1, SeqCst) == 1 {
+            self.receivers_done.store(true, SeqCst);
+            self.senders.wakeup_all(self.id());
+        }
+    }
+
+    /// Whether `send`/`try_send` should report this channel as disconnected:
+    /// either explicitly `close`d, or every `Receiver` has been dropped.
+    fn send_disconnected(&self) -> bool {
+        self.closed.load(SeqCst) || self.receivers_done.load(SeqCst)
+    }
+
+    /// Whether `recv`/`try_recv` should report this channel as disconnected:
+    /// either explicitly `close`d, or every `Sender` has been dropped.
+    fn recv_disconnected(&self) -> bool {
+        self.closed.load(SeqCst) || self.senders_done.load(SeqCst)
+    }
+}
+
+impl<T> Channel<T> for Zero<T> {
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        if self.send_disconnected() {
+            return Err(TrySendError::Disconnected(value));
+        }
+
+        // Checking `receivers.is_empty()` and then pushing a slot isn't a
+        // single atomic step, so in principle more senders than there are
+        // waiting receivers could all push a slot here; each pushed slot is
+        // still only ever claimed once (the receiver pops it under
+        // `waiting_senders`'s lock), it may just take a later receiver to
+        // come along and claim it instead of completing "instantly".
+        if self.receivers.is_empty() {
+            return Err(TrySendError::Full(value));
+        }
+
+        let slot = Arc::new(SenderSlot {
+            value: Mutex::new(Some(value)),
+        });
+        self.waiting_senders.lock().unwrap().push_back(slot);
+        self.receivers.wakeup_one(self.id());
+        Ok(())
+    }
+
+    fn send_until(
+        &self,
+        mut value: T,
+        deadline: Option<Instant>,
+    ) -> Result<(), SendTimeoutError<T>> {
+        loop {
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Disconnected(v)) => return Err(SendTimeoutError::Disconnected(v)),
+                Err(TrySendError::Full(v)) => value = v,
+            }
+
+            let now = Instant::now();
+            if let Some(end) = deadline {
+                if now >= end {
+                    return Err(SendTimeoutError::Timeout(value));
+                }
+            }
+
+            blocker::reset();
+            self.senders.register();
+
+            if !self.send_disconnected() && self.receivers.is_empty() {
+                if !blocker::wait_until(deadline) {
+                    self.senders.unregister();
+                    return Err(SendTimeoutError::Timeout(value));
+                }
+            } else {
+                self.senders.unregister();
+            }
+        }
+    }
+
+    fn try_recv(&self) -> Result<T, TryRecvError> {
+        let claimed = self.waiting_senders.lock().unwrap().pop_front();
+
+        match claimed {
+            Some(slot) => {
+                let value = slot
+                    .value
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .expect("a SenderSlot is only ever pushed once and claimed once");
+                self.senders.wakeup_one(self.id());
+                Ok(value)
+            }
+            None => {
+                if self.recv_disconnected() {
+                    Err(TryRecvError::Disconnected)
+                } else {
+                    Err(TryRecvError::Empty)
+                }
+            }
+        }
+    }
+
+    fn recv_until(&self, deadline: Option<Instant>) -> Result<T, RecvTimeoutError> {
+        loop {
+            // A registration from an earlier round that was never woken (e.g.
+            // after an early return from `wait_until`) is dropped on every
+            // return, so a finished `recv` never leaves one behind for
+            // `try_send` to see.
+            match self.try_recv() {
+                Ok(v) => {
+                    self.receivers.unregister();
+                    return Ok(v);
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.receivers.unregister();
+                    return Err(RecvTimeoutError::Disconnected);
+                }
+                Err(TryRecvError::Empty) => {}
+            }
+
+            let now = Instant::now();
+            if let Some(end) = deadline {
+                if now >= end {
+                    self.receivers.unregister();
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            }
+
+            blocker::reset();
+            self.receivers.register();
+
+            // Let a sender parked in `send_until` know a receiver is now ready.
+            self.senders.wakeup_one(self.id());
+
+            if !self.recv_disconnected() && self.waiting_senders.lock().unwrap().is_empty() {
+                if !blocker::wait_until(deadline) {
+                    self.receivers.unregister();
+                    return Err(RecvTimeoutError::Timeout);
+                }
+            } else {
+                self.receivers.unregister();
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        0
+    }
+
+    fn is_empty(&self) -> bool {
+        true
+    }
+
+    fn is_full(&self) -> bool {
+        true
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(0)
+    }
+
+    fn close(&self) -> bool {
+        if self.closed.swap(true, SeqCst) {
+            return false;
+        }
+
+        self.receivers.wakeup_all(self.id());
+        self.senders.wakeup_all(self.id());
+        true
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed.load(SeqCst) || self.senders_done.load(SeqCst) || self.receivers_done.load(SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use crossbeam;
+
+    use bounded;
+    use err::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn try_send_without_receiver_is_full() {
+        let (tx, _rx) = bounded(0);
+        assert_eq!(tx.try_send(1), Err(TrySendError::Full(1)));
+    }
+
+    #[test]
+    fn rendezvous() {
+        let (tx, rx) = bounded(0);
+
+        crossbeam::scope(|s| {
+            s.spawn(move || {
+                assert_eq!(rx.recv(), Ok(7));
+            });
+            s.spawn(move || {
+                thread::sleep(ms(100));
+                assert_eq!(tx.send(7), Ok(()));
+            });
+        });
+    }
+
+    #[test]
+    fn dropping_last_receiver_wakes_blocked_sender() {
+        let (tx, rx) = bounded(0);
+
+        crossbeam::scope(|s| {
+            s.spawn(move || {
+                assert_eq!(tx.send(1), Err(SendError(1)));
+            });
+            s.spawn(move || {
+                thread::sleep(ms(100));
+                drop(rx);
+            });
+        });
+    }
+
+    #[test]
+    fn many_rendezvous_in_a_row_dont_leak_a_waiting_receiver() {
+        let (tx, rx) = bounded(0);
+        let tx2 = tx.clone();
+        let rx2 = rx.clone();
+
+        crossbeam::scope(|s| {
+            s.spawn(move || {
+                for i in 0..50 {
+                    assert_eq!(rx.recv(), Ok(i));
+                }
+            });
+            s.spawn(move || {
+                for i in 0..50 {
+                    assert_eq!(tx.send(i), Ok(()));
+                }
+            });
+        });
+
+        // If a prior rendezvous had left a stale "receiver is waiting"
+        // registration behind, this would wrongly succeed with nobody left
+        // to claim it.
+        assert_eq!(tx2.try_send(1), Err(TrySendError::Full(1)));
+        drop(rx2);
+    }
+}
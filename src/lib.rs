@@ -0,0 +1,233 @@
+extern crate coco;
+extern crate crossbeam;
+
+pub mod blocker;
+pub mod err;
+pub mod impls;
+pub mod monitor;
+pub mod select;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use err::{RecvError, RecvTimeoutError, SendError, SendTimeoutError, TryRecvError, TrySendError};
+use impls::Channel;
+use impls::list::Queue;
+use impls::zero::Zero;
+use monitor::Monitor;
+
+/// The concrete channel implementation behind a `Sender`/`Receiver` pair.
+///
+/// `Sender`/`Receiver` dispatch every operation straight through to whichever
+/// variant is present, rather than picking a flavor with a generic parameter:
+/// `unbounded`/`bounded` decide once, at construction, and every clone of a
+/// handle shares the same `Arc<Flavor<T>>`.
+enum Flavor<T> {
+    List(Queue<T>),
+    Zero(Zero<T>),
+}
+
+impl<T> Flavor<T> {
+    fn channel(&self) -> &Channel<T> {
+        match *self {
+            Flavor::List(ref q) => q,
+            Flavor::Zero(ref z) => z,
+        }
+    }
+
+    fn monitor_rx(&self) -> &Monitor {
+        match *self {
+            Flavor::List(ref q) => q.monitor_rx(),
+            Flavor::Zero(ref z) => z.monitor_rx(),
+        }
+    }
+
+    fn monitor_tx(&self) -> &Monitor {
+        match *self {
+            Flavor::List(ref q) => q.monitor_tx(),
+            Flavor::Zero(ref z) => z.monitor_tx(),
+        }
+    }
+
+    /// Registers another live `Sender` handle.
+    fn acquire_sender(&self) {
+        match *self {
+            Flavor::List(ref q) => q.acquire_sender(),
+            Flavor::Zero(ref z) => z.acquire_sender(),
+        }
+    }
+
+    fn release_sender(&self) {
+        match *self {
+            Flavor::List(ref q) => q.release_sender(),
+            Flavor::Zero(ref z) => z.release_sender(),
+        }
+    }
+
+    fn acquire_receiver(&self) {
+        match *self {
+            Flavor::List(ref q) => q.acquire_receiver(),
+            Flavor::Zero(ref z) => z.acquire_receiver(),
+        }
+    }
+
+    fn release_receiver(&self) {
+        match *self {
+            Flavor::List(ref q) => q.release_receiver(),
+            Flavor::Zero(ref z) => z.release_receiver(),
+        }
+    }
+}
+
+/// The sending half of a channel, created by `unbounded` or `bounded`.
+///
+/// Cloning a `Sender` registers another live handle with the underlying
+/// flavor, and dropping the last one lets receivers observe disconnection
+/// instead of blocking (or buffering) for a sender that will never show up
+/// again.
+pub struct Sender<T> {
+    flavor: Arc<Flavor<T>>,
+}
+
+impl<T> Sender<T> {
+    fn channel(&self) -> &Channel<T> {
+        self.flavor.channel()
+    }
+
+    pub(crate) fn monitor_tx(&self) -> &Monitor {
+        self.flavor.monitor_tx()
+    }
+
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.channel().try_send(value)
+    }
+
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.channel().send(value)
+    }
+
+    pub fn send_timeout(&self, value: T, timeout: Duration) -> Result<(), SendTimeoutError<T>> {
+        self.channel().send_until(value, Some(Instant::now() + timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channel().is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.channel().is_full()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.channel().capacity()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.channel().is_closed()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        self.flavor.acquire_sender();
+        Sender { flavor: self.flavor.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.flavor.release_sender();
+    }
+}
+
+/// The receiving half of a channel, created by `unbounded` or `bounded`.
+///
+/// Cloning a `Receiver` registers another live handle with the underlying
+/// flavor, and dropping the last one lets senders observe disconnection
+/// instead of blocking (or buffering) for a receiver that will never show up
+/// again.
+pub struct Receiver<T> {
+    flavor: Arc<Flavor<T>>,
+}
+
+impl<T> Receiver<T> {
+    fn channel(&self) -> &Channel<T> {
+        self.flavor.channel()
+    }
+
+    pub(crate) fn monitor_rx(&self) -> &Monitor {
+        self.flavor.monitor_rx()
+    }
+
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.channel().try_recv()
+    }
+
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.channel().recv()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        self.channel().recv_until(Some(Instant::now() + timeout))
+    }
+
+    pub fn len(&self) -> usize {
+        self.channel().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channel().is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.channel().is_full()
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.channel().capacity()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.channel().is_closed()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Self {
+        self.flavor.acquire_receiver();
+        Receiver { flavor: self.flavor.clone() }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.flavor.release_receiver();
+    }
+}
+
+/// Creates an unbounded channel: `send`/`try_send` never block on capacity.
+pub fn unbounded<T>() -> (Sender<T>, Receiver<T>) {
+    let flavor = Arc::new(Flavor::List(Queue::new()));
+    (
+        Sender { flavor: flavor.clone() },
+        Receiver { flavor: flavor },
+    )
+}
+
+/// Creates a channel that holds at most `cap` items in transit, or a
+/// zero-capacity rendezvous channel when `cap` is `0`.
+pub fn bounded<T>(cap: usize) -> (Sender<T>, Receiver<T>) {
+    let flavor = if cap == 0 {
+        Arc::new(Flavor::Zero(Zero::new()))
+    } else {
+        Arc::new(Flavor::List(Queue::with_capacity(Some(cap))))
+    };
+    (
+        Sender { flavor: flavor.clone() },
+        Receiver { flavor: flavor },
+    )
+}
@@ -0,0 +1,79 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use blocker::{self, Blocker};
+
+/// One registered waiter: just the blocker to unpark when this monitor fires.
+struct Waiter {
+    blocker: Blocker,
+}
+
+/// Tracks who's currently parked waiting on a channel, and wakes them when
+/// the channel's state changes.
+///
+/// Waiters are stored as `Blocker`s rather than assumed to be OS threads, so
+/// the exact same `Queue`/`Zero`/`Select` code parks and wakes correctly
+/// whether the caller is a plain thread or a task scheduled by a coroutine
+/// runtime that installed its own `Blocker` via `blocker::set`.
+pub struct Monitor {
+    waiters: Mutex<VecDeque<Waiter>>,
+}
+
+impl Monitor {
+    pub fn new() -> Monitor {
+        Monitor {
+            waiters: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Registers the current blocker (see `blocker::current`) to be woken by
+    /// this monitor.
+    pub fn register(&self) {
+        let blocker = blocker::current();
+        self.waiters.lock().unwrap().push_back(Waiter { blocker: blocker });
+    }
+
+    /// Removes the current blocker's registration, e.g. after a non-blocking
+    /// retry found the channel ready without needing to park after all.
+    pub fn unregister(&self) {
+        let id = blocker::current().id();
+        self.waiters.lock().unwrap().retain(|w| w.blocker.id() != id);
+    }
+
+    /// Wakes one registered waiter other than `exclude`, if any, unparking
+    /// whatever blocker kind it is and removing it from the registration
+    /// list.
+    pub fn wakeup_one(&self, exclude: usize) {
+        let mut waiters = self.waiters.lock().unwrap();
+        let pos = waiters.iter().position(|w| w.blocker.id() != exclude);
+        if let Some(pos) = pos {
+            let waiter = waiters.remove(pos).unwrap();
+            waiter.blocker.unpark();
+        }
+    }
+
+    /// Wakes every registered waiter other than `exclude`, unparking each
+    /// one's blocker and removing it from the registration list.
+    pub fn wakeup_all(&self, exclude: usize) {
+        let mut waiters = self.waiters.lock().unwrap();
+        let mut remaining = VecDeque::with_capacity(waiters.len());
+
+        while let Some(waiter) = waiters.pop_front() {
+            if waiter.blocker.id() != exclude {
+                waiter.blocker.unpark();
+            } else {
+                remaining.push_back(waiter);
+            }
+        }
+
+        *waiters = remaining;
+    }
+
+    /// Whether anyone is currently registered. Lets a channel flavor (e.g.
+    /// the zero-capacity rendezvous channel) ask "is anyone parked on the
+    /// other side" directly from the registration list, instead of keeping
+    /// its own side counter that can drift out of sync with it.
+    pub fn is_empty(&self) -> bool {
+        self.waiters.lock().unwrap().is_empty()
+    }
+}
@@ -0,0 +1,257 @@
+use std::time::Instant;
+
+use blocker;
+use err::{RecvError, SendError, TryRecvError, TrySendError};
+use monitor::Monitor;
+use {Receiver, Sender};
+
+/// What a case's non-blocking attempt discovered.
+enum CaseOutcome {
+    /// The case completed and its callback already ran.
+    Ready,
+    /// The case couldn't make progress right now.
+    Blocked,
+}
+
+/// One case registered with a `Select`: a stable id (unaffected by other
+/// cases winning and being removed), a monitor to park on, and a closure
+/// that performs the case's non-blocking operation and reports the outcome
+/// through the callback the caller supplied when registering it.
+struct Case<'a> {
+    id: usize,
+    monitor: &'a Monitor,
+    attempt: Box<FnMut() -> CaseOutcome + 'a>,
+}
+
+/// Waits on several send/receive cases at once and proceeds with whichever
+/// becomes ready first.
+///
+/// Built on top of the same `Monitor`/`blocker` machinery as the channel
+/// flavors themselves: `ready` registers the current blocker on every case's
+/// monitor, repeatedly tries each case in a rotating order (so no single
+/// case can starve the others), and parks via `blocker::wait_until` between
+/// rounds until one case completes or the deadline elapses.
+pub struct Select<'a> {
+    cases: Vec<Case<'a>>,
+    next: usize,
+    next_id: usize,
+}
+
+impl<'a> Select<'a> {
+    pub fn new() -> Self {
+        Select {
+            cases: Vec::new(),
+            next: 0,
+            next_id: 0,
+        }
+    }
+
+    fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Registers a receive case. `on_ready` is called with the outcome of
+    /// `try_recv` exactly once, if and only if this case wins.
+    pub fn recv<T, F>(&mut self, rx: &'a Receiver<T>, mut on_ready: F)
+    where
+        F: FnMut(Result<T, RecvError>) + 'a,
+    {
+        let id = self.next_id();
+        self.cases.push(Case {
+            id: id,
+            monitor: rx.monitor_rx(),
+            attempt: Box::new(move || match rx.try_recv() {
+                Ok(v) => {
+                    on_ready(Ok(v));
+                    CaseOutcome::Ready
+                }
+                Err(TryRecvError::Disconnected) => {
+                    on_ready(Err(RecvError));
+                    CaseOutcome::Ready
+                }
+                Err(TryRecvError::Empty) => CaseOutcome::Blocked,
+            }),
+        });
+    }
+
+    /// Registers a send case. `on_ready` is called with the outcome of
+    /// `try_send` exactly once, if and only if this case wins.
+    pub fn send<T, F>(&mut self, tx: &'a Sender<T>, mut value: Option<T>, mut on_ready: F)
+    where
+        F: FnMut(Result<(), SendError<T>>) + 'a,
+    {
+        let id = self.next_id();
+        self.cases.push(Case {
+            id: id,
+            monitor: tx.monitor_tx(),
+            attempt: Box::new(move || {
+                let v = value.take().expect("a send case's attempt only runs again \
+                    after `Blocked`, which always restores `value`");
+                match tx.try_send(v) {
+                    Ok(()) => {
+                        on_ready(Ok(()));
+                        CaseOutcome::Ready
+                    }
+                    Err(TrySendError::Disconnected(v)) => {
+                        on_ready(Err(SendError(v)));
+                        CaseOutcome::Ready
+                    }
+                    Err(TrySendError::Full(v)) => {
+                        value = Some(v);
+                        CaseOutcome::Blocked
+                    }
+                }
+            }),
+        });
+    }
+
+    /// Blocks until one of the registered cases becomes ready (or `deadline`
+    /// elapses), and returns the id of the case that won — the position it
+    /// was registered at via `recv`/`send`, counting from 0.
+    ///
+    /// The winning case is removed from this `Select`: its callback already
+    /// ran, so there's nothing left for it to do on a later `ready()` call on
+    /// the same `Select`, and removing it means a returned id always refers
+    /// to a case that's actually still live. Remaining cases keep their
+    /// original ids.
+    ///
+    /// A closed channel makes its case immediately ready with a
+    /// `Disconnected`/`SendError` outcome, same as any other completion.
+    pub fn ready(&mut self, deadline: Option<Instant>) -> Option<usize> {
+        if self.cases.is_empty() {
+            return None;
+        }
+
+        blocker::reset();
+        for case in &self.cases {
+            case.monitor.register();
+        }
+
+        let len = self.cases.len();
+        let start = self.next % len;
+        self.next = start + 1;
+
+        let won = loop {
+            let mut won = None;
+
+            for offset in 0..len {
+                let i = (start + offset) % len;
+                if let CaseOutcome::Ready = (self.cases[i].attempt)() {
+                    won = Some(i);
+                    break;
+                }
+            }
+
+            if won.is_some() {
+                break won;
+            }
+
+            if let Some(end) = deadline {
+                if Instant::now() >= end {
+                    break None;
+                }
+            }
+
+            if !blocker::wait_until(deadline) {
+                break None;
+            }
+        };
+
+        for case in &self.cases {
+            case.monitor.unregister();
+        }
+
+        won.map(|i| self.cases.remove(i).id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crossbeam;
+
+    use {bounded, unbounded};
+    use err::*;
+
+    use super::Select;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn ready_blocks_then_wakes_on_whichever_channel_fires() {
+        let (tx1, rx1) = unbounded::<i32>();
+        let (tx2, rx2) = unbounded::<i32>();
+
+        crossbeam::scope(|s| {
+            s.spawn(move || {
+                thread::sleep(ms(100));
+                assert_eq!(tx2.send(7), Ok(()));
+            });
+
+            let mut got1 = None;
+            let mut got2 = None;
+            let mut sel = Select::new();
+            sel.recv(&rx1, |r| got1 = Some(r));
+            sel.recv(&rx2, |r| got2 = Some(r));
+
+            let won = sel.ready(None);
+            drop(sel);
+
+            assert_eq!(won, Some(1));
+            assert_eq!(got1, None);
+            assert_eq!(got2, Some(Ok(7)));
+            drop(tx1);
+        });
+    }
+
+    #[test]
+    fn ready_resolves_closed_channel_immediately() {
+        let (tx, rx) = unbounded::<i32>();
+        drop(tx);
+
+        let mut result = None;
+        let mut sel = Select::new();
+        sel.recv(&rx, |r| result = Some(r));
+
+        let won = sel.ready(None);
+        drop(sel);
+
+        assert_eq!(won, Some(0));
+        assert_eq!(result, Some(Err(RecvError)));
+    }
+
+    #[test]
+    fn ready_can_be_called_again_after_a_send_case_wins() {
+        let (tx1, rx1) = bounded::<i32>(1);
+        let (tx2, _rx2) = bounded::<i32>(1);
+        // Fill tx2 up front so its case is still `Blocked` once tx1's wins.
+        tx2.send(0).unwrap();
+
+        let mut sent1 = false;
+        let mut sent2 = false;
+        let mut sel = Select::new();
+        sel.send(&tx1, Some(1), |r| sent1 = r.is_ok());
+        sel.send(&tx2, Some(2), |r| sent2 = r.is_ok());
+
+        let first = sel.ready(None);
+
+        // tx1's case won and was removed from the rotation; calling ready()
+        // again on the same Select must not panic by re-running its
+        // attempt (the bug this regresses), and should just time out since
+        // tx2 is still full.
+        let second = sel.ready(Some(Instant::now() + ms(50)));
+        drop(sel);
+
+        assert_eq!(first, Some(0));
+        assert!(sent1);
+        assert_eq!(rx1.try_recv(), Ok(1));
+        assert_eq!(second, None);
+        assert!(!sent2);
+    }
+}